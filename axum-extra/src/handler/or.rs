@@ -16,6 +16,29 @@ pub struct Or<L, R, Lt, Rt, B> {
     pub(super) _marker: PhantomData<fn() -> (Lt, Rt, B)>,
 }
 
+impl<L, R, Lt, Rt, B> Or<L, R, Lt, Rt, B> {
+    /// Customize what happens when this `Or`'s two branches both reject the request.
+    ///
+    /// By default a rejected `Or` falls back to a bare `404 Not Found`, discarding whichever
+    /// rejections the branches actually produced. `or_else_respond` instead collects those two
+    /// rejections into [`Rejections`], in the order they were tried, and passes them to `f` to
+    /// build the final response — e.g. returning the most specific rejection, or a
+    /// `406 Not Acceptable` for content-negotiation style dispatch between `Json` and `Form`.
+    ///
+    /// Note that if `self` was itself built with `.or(...)` (so `lhs` is a nested `Or`), that
+    /// nested chain is still a single `Lt` as far as this method is concerned: its own extractors
+    /// are tried internally and collapsed into one `Either` rejection before `or_else_respond`
+    /// ever sees it, so `Rejections` always has (at most) two entries here, not one per leaf
+    /// extractor in a longer chain. For genuinely independent rejections across more than two
+    /// branches, build the chain with [`or_many`] instead of `.or(...).or(...)`.
+    pub fn or_else_respond<F>(self, f: F) -> OrElseRespond<Self, F>
+    where
+        F: Fn(Rejections) -> Response + Clone + Send + 'static,
+    {
+        OrElseRespond { inner: self, f }
+    }
+}
+
 impl<B, L, R, Lt, Rt> HandlerCallWithExtractors<Either<Lt, Rt>, B> for Or<L, R, Lt, Rt, B>
 where
     L: HandlerCallWithExtractors<Lt, B> + Send + 'static,
@@ -89,6 +112,186 @@ where
     }
 }
 
+/// The rejections collected from an [`Or`]'s two branches when both rejected the request, in the
+/// order they were tried.
+///
+/// Passed to the closure given to [`Or::or_else_respond`]. Note this always has (at most) two
+/// entries, even when `or_else_respond` is called on the end of a longer `.or(...).or(...)`
+/// chain — see [`Or::or_else_respond`].
+#[allow(missing_debug_implementations)]
+pub struct Rejections(pub Vec<Response>);
+
+impl Rejections {
+    /// Iterate over the collected rejection responses, in the order the branches were tried.
+    pub fn iter(&self) -> impl Iterator<Item = &Response> {
+        self.0.iter()
+    }
+
+    /// The number of rejections collected, i.e. the number of branches that were tried.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether any rejections were collected.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// An [`Or`] combinator with a custom terminal response for when every branch rejects.
+///
+/// See [`Or::or_else_respond`].
+#[allow(missing_debug_implementations)]
+pub struct OrElseRespond<O, F> {
+    inner: O,
+    f: F,
+}
+
+impl<O, F> Clone for OrElseRespond<O, F>
+where
+    O: Clone,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<B, L, R, Lt, Rt, F> Handler<(Lt, Rt), B> for OrElseRespond<Or<L, R, Lt, Rt, B>, F>
+where
+    L: HandlerCallWithExtractors<Lt, B> + Clone + Send + 'static,
+    R: HandlerCallWithExtractors<Rt, B> + Clone + Send + 'static,
+    Lt: FromRequest<B> + Send + 'static,
+    Rt: FromRequest<B> + Send + 'static,
+    Lt::Rejection: IntoResponse + Send,
+    Rt::Rejection: IntoResponse + Send,
+    B: Send + 'static,
+    F: Fn(Rejections) -> Response + Clone + Send + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, req: Request<B>) -> Self::Future {
+        let Or { lhs, rhs, .. } = self.inner;
+        let f = self.f;
+
+        Box::pin(async move {
+            let mut req = RequestParts::new(req);
+            let mut rejections = Vec::with_capacity(2);
+
+            match req.extract::<Lt>().await {
+                Ok(lt) => return lhs.call(lt).await,
+                Err(rejection) => rejections.push(rejection.into_response()),
+            }
+
+            match req.extract::<Rt>().await {
+                Ok(rt) => return rhs.call(rt).await,
+                Err(rejection) => rejections.push(rejection.into_response()),
+            }
+
+            f(Rejections(rejections))
+        })
+    }
+}
+
+/// Try each handler in `handlers` against the request, in order, falling back to the next one on
+/// rejection.
+///
+/// Unlike chaining `.or(...)` more than once, each handler's extractor is tried independently
+/// here, so every branch's rejection survives to [`OrMany::or_else_respond`] no matter how many
+/// branches there are — `.or(...).or(...)` collapses earlier branches into a single `Either`
+/// rejection before [`Or::or_else_respond`] ever sees them, which `or_many` avoids by tying every
+/// branch's handler directly to its own extractor type up front, e.g. for content-negotiation
+/// style dispatch across `Json`, `Form`, and `Query` extractors.
+///
+/// By default a chain that rejects on every branch falls back to a bare `404 Not Found`, the same
+/// as [`Or`]; use [`OrMany::or_else_respond`] to customize that.
+pub fn or_many<T>(handlers: T) -> OrMany<T, fn(Rejections) -> Response> {
+    OrMany {
+        handlers,
+        f: |_: Rejections| StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// See [`or_many`].
+#[allow(missing_debug_implementations)]
+pub struct OrMany<T, F> {
+    handlers: T,
+    f: F,
+}
+
+impl<T, F> OrMany<T, F> {
+    /// Customize what happens when every handler in this chain rejects the request.
+    ///
+    /// Mirrors [`Or::or_else_respond`], but since `or_many`'s branches were never collapsed into
+    /// nested `Either`s to begin with, [`Rejections`] here always has one entry per handler in the
+    /// chain.
+    pub fn or_else_respond<G>(self, f: G) -> OrMany<T, G>
+    where
+        G: Fn(Rejections) -> Response + Clone + Send + 'static,
+    {
+        OrMany {
+            handlers: self.handlers,
+            f,
+        }
+    }
+}
+
+impl<T, F> Clone for OrMany<T, F>
+where
+    T: Clone,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            handlers: self.handlers.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+macro_rules! impl_or_many {
+    ($($H:ident, $T:ident),+) => {
+        impl<B, F, $($H, $T),+> Handler<($($T,)+), B> for OrMany<($($H,)+), F>
+        where
+            $( $H: HandlerCallWithExtractors<$T, B> + Clone + Send + 'static, )+
+            $( $T: FromRequest<B> + Send + 'static, )+
+            $( $T::Rejection: IntoResponse + Send, )+
+            F: Fn(Rejections) -> Response + Clone + Send + 'static,
+            B: Send + 'static,
+        {
+            type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+            #[allow(non_snake_case)]
+            fn call(self, req: Request<B>) -> Self::Future {
+                let ($($H,)+) = self.handlers;
+                let f = self.f;
+
+                Box::pin(async move {
+                    let mut req = RequestParts::new(req);
+                    let mut rejections = Vec::new();
+
+                    $(
+                        match req.extract::<$T>().await {
+                            Ok(extracted) => return $H.call(extracted).await,
+                            Err(rejection) => rejections.push(rejection.into_response()),
+                        }
+                    )+
+
+                    f(Rejections(rejections))
+                })
+            }
+        }
+    };
+}
+
+impl_or_many!(H1, T1, H2, T2);
+impl_or_many!(H1, T1, H2, T2, H3, T3);
+impl_or_many!(H1, T1, H2, T2, H3, T3, H4, T4);
+impl_or_many!(H1, T1, H2, T2, H3, T3, H4, T4, H5, T5);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,25 +303,34 @@ mod tests {
     };
     use serde::Deserialize;
 
-    #[tokio::test]
-    async fn works() {
-        #[derive(Deserialize)]
-        struct Params {
-            a: String,
-        }
+    #[derive(Deserialize)]
+    struct Params {
+        a: String,
+    }
 
-        async fn one(Path(id): Path<u32>) -> String {
-            id.to_string()
-        }
+    #[derive(Deserialize)]
+    struct OtherParams {
+        b: String,
+    }
 
-        async fn two(Query(params): Query<Params>) -> String {
-            params.a
-        }
+    async fn one(Path(id): Path<u32>) -> String {
+        id.to_string()
+    }
 
-        async fn three() -> &'static str {
-            "fallback"
-        }
+    async fn two(Query(params): Query<Params>) -> String {
+        params.a
+    }
+
+    async fn three() -> &'static str {
+        "fallback"
+    }
 
+    async fn four(Query(params): Query<OtherParams>) -> String {
+        params.b
+    }
+
+    #[tokio::test]
+    async fn works() {
         let app = Router::new().route("/:id", get(one.or(two).or(three)));
 
         let client = TestClient::new(app);
@@ -132,4 +344,80 @@ mod tests {
         let res = client.get("/foo").send().await;
         assert_eq!(res.text().await, "fallback");
     }
+
+    #[tokio::test]
+    async fn or_else_respond_sees_every_rejection() {
+        let app = Router::new().route(
+            "/:id",
+            get(one.or(two).or_else_respond(|rejections| {
+                assert_eq!(rejections.len(), 2);
+                (StatusCode::NOT_ACCEPTABLE, "no extractor matched").into_response()
+            })),
+        );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/foo").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.text().await, "no extractor matched");
+    }
+
+    #[tokio::test]
+    async fn or_else_respond_on_a_longer_chain_still_sees_only_two_rejections() {
+        // `one.or(two)` is collapsed into a single `Lt` by the time the outer `Or` (built by the
+        // second `.or(four)`) tries it, so `or_else_respond` here still only ever collects two
+        // rejections — one standing in for the whole `one.or(two)` chain, and one for `four` —
+        // not one per leaf extractor in the chain.
+        let app = Router::new().route(
+            "/:id",
+            get(one.or(two).or(four).or_else_respond(|rejections| {
+                assert_eq!(rejections.len(), 2);
+                (StatusCode::NOT_ACCEPTABLE, "no extractor matched").into_response()
+            })),
+        );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/foo").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.text().await, "no extractor matched");
+    }
+
+    #[tokio::test]
+    async fn or_many_sees_every_branchs_rejection() {
+        let app = Router::new().route(
+            "/:id",
+            get(
+                or_many((one, two, four)).or_else_respond(|rejections| {
+                    assert_eq!(rejections.len(), 3);
+                    (StatusCode::NOT_ACCEPTABLE, "no extractor matched").into_response()
+                }),
+            ),
+        );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/foo").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.text().await, "no extractor matched");
+    }
+
+    #[tokio::test]
+    async fn or_many_falls_through_to_the_matching_branch() {
+        let app = Router::new().route("/:id", get(or_many((one, two, four))));
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/123").send().await;
+        assert_eq!(res.text().await, "123");
+
+        let res = client.get("/foo?a=bar").send().await;
+        assert_eq!(res.text().await, "bar");
+
+        let res = client.get("/foo?b=baz").send().await;
+        assert_eq!(res.text().await, "baz");
+
+        let res = client.get("/foo").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
 }
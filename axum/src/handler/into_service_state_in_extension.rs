@@ -1,12 +1,16 @@
 use super::Handler;
-use crate::response::Response;
-use http::Request;
+use crate::response::{IntoResponse, Response};
+use http::{request::Parts, Request, StatusCode};
+use pin_project_lite::pin_project;
 use std::{
     convert::Infallible,
     fmt,
+    future::Future,
     marker::PhantomData,
+    pin::Pin,
     task::{Context, Poll},
 };
+use tower_layer::Layer;
 use tower_service::Service;
 
 pub(crate) struct IntoServiceStateInExtension<H, T, S, B> {
@@ -58,7 +62,7 @@ where
 {
     type Response = Response;
     type Error = Infallible;
-    type Future = super::future::IntoServiceFuture<H::Future>;
+    type Future = IntoServiceStateInExtensionFuture<H::Future>;
 
     #[inline]
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -69,20 +73,261 @@ where
     }
 
     fn call(&mut self, req: Request<B>) -> Self::Future {
-        use futures_util::future::FutureExt;
+        match req.extensions().get::<S>().cloned() {
+            Some(state) => {
+                let handler = self.handler.clone();
+                let future = Handler::call(handler, req, state);
+                IntoServiceStateInExtensionFuture::Handler { future }
+            }
+            None => IntoServiceStateInExtensionFuture::StateMissing {
+                response: Some(StateExtensionMissing.into_response()),
+            },
+        }
+    }
+}
+
+/// The error returned when a request reaches an [`IntoServiceStateInExtension`] without `S`
+/// having been inserted into the request extensions first.
+///
+/// This happens when no [`ProvideState`] layer (or other middleware that inserts `S`) ran
+/// before the handler, and is a bug in how the `Router`/middleware stack was assembled rather
+/// than something callers of the resulting service can trigger.
+#[derive(Debug)]
+pub(crate) struct StateExtensionMissing;
+
+impl IntoResponse for StateExtensionMissing {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "state extension missing. This is a bug in axum, please file an issue",
+        )
+            .into_response()
+    }
+}
+
+pin_project! {
+    /// Response future for [`IntoServiceStateInExtension`].
+    #[project = IntoServiceStateInExtensionFutureProj]
+    pub(crate) enum IntoServiceStateInExtensionFuture<F> {
+        Handler {
+            #[pin]
+            future: F,
+        },
+        StateMissing {
+            response: Option<Response>,
+        },
+    }
+}
+
+impl<F> Future for IntoServiceStateInExtensionFuture<F>
+where
+    F: Future<Output = Response>,
+{
+    type Output = Result<Response, Infallible>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            IntoServiceStateInExtensionFutureProj::Handler { future } => future.poll(cx).map(Ok),
+            IntoServiceStateInExtensionFutureProj::StateMissing { response } => {
+                Poll::Ready(Ok(response.take().expect("future polled after completion")))
+            }
+        }
+    }
+}
+
+/// Produces a per-request `S` before a handler wrapped in [`IntoServiceStateInExtension`] runs.
+///
+/// Unlike a `Router`'s single `S` baked in at build time, a `StateFactory` is invoked on every
+/// request, which makes it possible to build state that depends on the incoming request itself
+/// (per-tenant config resolved from a header, a per-connection database handle, and so on).
+///
+/// Install one with the [`ProvideState`] layer.
+pub(crate) trait StateFactory<S>: Clone + Send + Sync + 'static {
+    /// The future returned by [`StateFactory::create`].
+    type Future: Future<Output = Result<S, Response>> + Send + 'static;
+
+    /// Create the state for this request, or short-circuit with a `Response` (e.g. a `401` when
+    /// the request doesn't carry what's needed to build `S`).
+    fn create(&self, parts: &mut Parts) -> Self::Future;
+}
+
+/// A [`tower::Layer`](tower_layer::Layer) that runs a [`StateFactory`] and inserts the `S` it
+/// produces into the request extensions, so that a downstream [`IntoServiceStateInExtension`]
+/// can pick it back up.
+#[derive(Clone)]
+pub(crate) struct ProvideStateLayer<F, S> {
+    factory: F,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<F, S> ProvideStateLayer<F, S> {
+    pub(crate) fn new(factory: F) -> Self {
+        Self {
+            factory,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, S> fmt::Debug for ProvideStateLayer<F, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvideStateLayer").finish()
+    }
+}
+
+impl<T, F, S> Layer<T> for ProvideStateLayer<F, S>
+where
+    F: Clone,
+{
+    type Service = ProvideState<F, S, T>;
+
+    fn layer(&self, inner: T) -> Self::Service {
+        ProvideState {
+            factory: self.factory.clone(),
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// See [`ProvideStateLayer`].
+pub(crate) struct ProvideState<F, S, T> {
+    factory: F,
+    inner: T,
+    _marker: PhantomData<fn() -> S>,
+}
 
-        let state = req
-            .extensions()
-            .get::<S>()
-            .expect("state extension missing. This is a bug in axum, please file an issue")
-            .clone();
+impl<F, S, T> Clone for ProvideState<F, S, T>
+where
+    F: Clone,
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, S, T> fmt::Debug for ProvideState<F, S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProvideState").finish()
+    }
+}
+
+impl<F, S, T, B> Service<Request<B>> for ProvideState<F, S, T>
+where
+    F: StateFactory<S>,
+    S: Clone + Send + Sync + 'static,
+    T: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    T::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = T::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, T::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let factory = self.factory.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            let state = match factory.create(&mut parts).await {
+                Ok(state) => state,
+                Err(response) => return Ok(response),
+            };
+            parts.extensions.insert(state);
+
+            let req = Request::from_parts(parts, body);
+            inner.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+
+    #[derive(Clone)]
+    struct Echo;
+
+    impl Handler<(), u32, Body> for Echo {
+        type Future = std::future::Ready<Response>;
+
+        fn call(self, _req: Request<Body>, state: u32) -> Self::Future {
+            std::future::ready(state.to_string().into_response())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Constant(u32);
+
+    impl StateFactory<u32> for Constant {
+        type Future = std::future::Ready<Result<u32, Response>>;
+
+        fn create(&self, _parts: &mut Parts) -> Self::Future {
+            std::future::ready(Ok(self.0))
+        }
+    }
+
+    #[derive(Clone)]
+    struct AlwaysReject;
+
+    impl StateFactory<u32> for AlwaysReject {
+        type Future = std::future::Ready<Result<u32, Response>>;
+
+        fn create(&self, _parts: &mut Parts) -> Self::Future {
+            std::future::ready(Err(StatusCode::UNAUTHORIZED.into_response()))
+        }
+    }
+
+    async fn body_string(res: Response) -> String {
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn provide_state_flows_into_handler() {
+        let mut svc =
+            ProvideStateLayer::new(Constant(42)).layer(IntoServiceStateInExtension::new(Echo));
+
+        let res = svc.call(Request::new(Body::empty())).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body_string(res).await, "42");
+    }
+
+    #[tokio::test]
+    async fn factory_failure_short_circuits_before_the_handler() {
+        let mut svc = ProvideStateLayer::new(AlwaysReject)
+            .layer(IntoServiceStateInExtension::new(Echo));
+
+        let res = svc.call(Request::new(Body::empty())).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
 
-        todo!()
+    #[tokio::test]
+    async fn state_missing_without_a_provide_state_layer() {
+        let mut svc: IntoServiceStateInExtension<Echo, (), u32, Body> =
+            IntoServiceStateInExtension::new(Echo);
 
-        // let handler = self.handler.clone();
-        // let future = Handler::call(handler, req);
-        // let future = future.map(Ok as _);
+        let res = svc.call(Request::new(Body::empty())).await.unwrap();
 
-        // super::future::IntoServiceFuture::new(future)
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body_string(res).await,
+            "state extension missing. This is a bug in axum, please file an issue"
+        );
     }
 }
@@ -8,8 +8,11 @@ use http::{
     header::{self, CONTENT_LENGTH},
     HeaderValue, Request,
 };
+use hyper::upgrade::{OnUpgrade, Upgraded};
 use pin_project_lite::pin_project;
 use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
     convert::Infallible,
     fmt,
     future::Future,
@@ -26,7 +29,21 @@ use tower_service::Service;
 ///
 /// You normally shouldn't need to care about this type. It's used in
 /// [`Router::layer`](super::Router::layer).
-pub struct Route<E = Infallible>(BoxCloneService<Request<Body>, Response, E>);
+pub struct Route<E = Infallible>(RouteInner<E>);
+
+/// The two shapes a [`Route`] can hold.
+///
+/// `Router::layer` wraps the current route in whatever `tower::Layer` the caller supplied, and
+/// that usually produces a brand new service type, which has to go back through
+/// [`BoxCloneService`] to keep `Route`'s type erased. But some layers just hand back a `Route<E>`
+/// unchanged (or wrapped in something that resolves to the same type), and in that case boxing
+/// again would add a second layer of dynamic dispatch and allocation for no reason. `Boxed` is
+/// the normal case; `Nested` lets `Route::new` skip the redundant box when it can prove (via
+/// `Any`) that the service it was handed already *is* a `Route<E>`.
+enum RouteInner<E> {
+    Boxed(BoxCloneService<Request<Body>, Response, E>),
+    Nested(Box<Route<E>>),
+}
 
 impl<E> Route<E> {
     pub(super) fn new<T>(svc: T) -> Self
@@ -34,20 +51,43 @@ impl<E> Route<E> {
         T: Service<Request<Body>, Response = Response, Error = E> + Clone + Send + 'static,
         T::Future: Send + 'static,
     {
-        Self(BoxCloneService::new(svc))
+        match Self::downcast(svc) {
+            Ok(route) => Self(RouteInner::Nested(Box::new(route))),
+            Err(svc) => Self(RouteInner::Boxed(BoxCloneService::new(svc))),
+        }
     }
 
-    pub(crate) fn oneshot_inner(
-        &mut self,
-        req: Request<Body>,
-    ) -> Oneshot<BoxCloneService<Request<Body>, Response, E>, Request<Body>> {
-        self.0.clone().oneshot(req)
+    /// If `svc` is already a `Route<E>`, hand it back unboxed. Otherwise hand back `svc`
+    /// unchanged so the caller can box it normally.
+    ///
+    /// `T` is only known to be `Service<...> + Clone + Send + 'static` at this point, so there's
+    /// no way to match on it directly; going through `Box<dyn Any>` is the standard way to ask
+    /// "is this generic type actually this concrete type" without specialization.
+    fn downcast<T>(svc: T) -> Result<Self, T>
+    where
+        T: 'static,
+    {
+        let svc: Box<dyn Any> = Box::new(svc);
+        match svc.downcast::<Self>() {
+            Ok(route) => Ok(*route),
+            Err(svc) => Err(*svc.downcast::<T>().expect("type cannot have changed")),
+        }
+    }
+
+    pub(crate) fn oneshot_inner(&mut self, req: Request<Body>) -> RouteFuture<E> {
+        match &mut self.0 {
+            RouteInner::Boxed(svc) => RouteFuture::from_future(svc.clone().oneshot(req)),
+            RouteInner::Nested(route) => RouteFuture::from_nested(route.oneshot_inner(req)),
+        }
     }
 }
 
 impl<E> Clone for Route<E> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        match &self.0 {
+            RouteInner::Boxed(svc) => Self(RouteInner::Boxed(svc.clone())),
+            RouteInner::Nested(route) => Self(RouteInner::Nested(route.clone())),
+        }
     }
 }
 
@@ -73,8 +113,12 @@ where
 
     #[inline]
     fn call(&mut self, req: Request<B>) -> Self::Future {
-        let req = req.map(Body::wrap_body);
-        RouteFuture::from_future(self.oneshot_inner(req))
+        let mut req = req.map(Body::wrap_body);
+        // If hyper flagged this as an upgrade request, take the `OnUpgrade` now, before the
+        // request is handed off to the inner service, so it can be handed to whatever
+        // `ResponseUpgrade` callback the handler's response comes back with.
+        let on_upgrade = req.extensions_mut().remove::<OnUpgrade>();
+        self.oneshot_inner(req).on_upgrade(on_upgrade)
     }
 }
 
@@ -85,6 +129,32 @@ pin_project! {
         kind: RouteFutureKind<E>,
         strip_body: bool,
         allow_header: Option<Bytes>,
+        on_upgrade: Option<OnUpgrade>,
+    }
+}
+
+/// Hands the connection off to `callback` once the response head has been written, for
+/// `101 Switching Protocols` flows (WebSocket, HTTP/2 CONNECT, or any other framed protocol).
+///
+/// Insert this into a handler's `Response` extensions to opt in; `RouteFuture` recognizes it,
+/// skips the usual `Content-Length`/body-stripping post-processing for that response, and runs
+/// `callback` once hyper reports the connection as upgraded. This keeps handlers from needing to
+/// reach into hyper's upgrade API directly.
+pub(crate) struct ResponseUpgrade(
+    Box<dyn FnOnce(Upgraded) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>,
+);
+
+impl ResponseUpgrade {
+    pub(crate) fn new<F, Fut>(callback: F) -> Self
+    where
+        F: FnOnce(Upgraded) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self(Box::new(move |upgraded| Box::pin(callback(upgraded))))
+    }
+
+    fn run(self, upgraded: Upgraded) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        (self.0)(upgraded)
     }
 }
 
@@ -98,12 +168,136 @@ pin_project! {
                 Request<Body>,
             >,
         },
+        Nested {
+            future: PooledRouteFuture<E>,
+        },
         Response {
             response: Option<Response>,
         }
     }
 }
 
+thread_local! {
+    // Only ever holds `RouteFuture<Infallible>` boxes (see `PooledRouteFuture`), which is by far
+    // the most common `E` in practice since `Route`'s default error type is `Infallible`.
+    static NESTED_ROUTE_FUTURE_POOL: RefCell<Vec<Pin<Box<RouteFuture<Infallible>>>>> =
+        RefCell::new(Vec::new());
+}
+
+// Capped so a burst of deeply-nested routers can't grow the pool unboundedly under sustained
+// traffic; it just means the excess falls back to a normal allocation instead of being reused.
+const NESTED_ROUTE_FUTURE_POOL_CAP: usize = 128;
+
+// A debug escape hatch for whoever ends up chasing a pooling-related bug: rebuild with
+// `--cfg feature="disable-route-pooling"` (via `RUSTFLAGS`, since this crate's `Cargo.toml` has
+// no `[features]` table declaring it yet — add `disable-route-pooling = []` there to let it be
+// selected normally) to confirm whether the pool, rather than the handler itself, is responsible.
+#[cfg(not(feature = "disable-route-pooling"))]
+fn pooling_enabled() -> bool {
+    true
+}
+
+#[cfg(feature = "disable-route-pooling")]
+fn pooling_enabled() -> bool {
+    false
+}
+
+/// The boxed allocation backing [`RouteFutureKind::Nested`].
+///
+/// Every layer added via `Router::layer` used to box its own `Oneshot` future; flattening (see
+/// `Route::new`) cut that down to one box per nested level, but a deep middleware stack still
+/// boxes a fresh `RouteFuture` on every request. Since `E` is almost always `Infallible`, those
+/// boxes are identically-shaped allocations that get dropped moments after being polled to
+/// completion — a natural fit for a small thread-local pool, so the common request reuses an
+/// existing allocation instead of going back to the global allocator.
+struct PooledRouteFuture<E> {
+    // `None` only ever observed transiently inside `Drop`/`poll` after the inner future has
+    // already been returned to the pool.
+    future: Option<Pin<Box<RouteFuture<E>>>>,
+}
+
+impl<E> PooledRouteFuture<E>
+where
+    E: 'static,
+{
+    fn new(future: RouteFuture<E>) -> Self {
+        let future = match checkout::<E>() {
+            Some(mut reused) => {
+                reused.as_mut().set(future);
+                reused
+            }
+            None => Box::pin(future),
+        };
+
+        Self {
+            future: Some(future),
+        }
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<Response, E>> {
+        self.future
+            .as_mut()
+            .expect("polled `PooledRouteFuture` after it returned its future to the pool")
+            .as_mut()
+            .poll(cx)
+    }
+}
+
+impl<E> Drop for PooledRouteFuture<E>
+where
+    E: 'static,
+{
+    fn drop(&mut self) {
+        // Run on every drop, not just after `Poll::Ready`, so a `RouteFuture` that's cancelled
+        // mid-flight (e.g. the client disconnects) still gives its allocation back.
+        if let Some(future) = self.future.take() {
+            checkin(future);
+        }
+    }
+}
+
+fn checkout<E>() -> Option<Pin<Box<RouteFuture<E>>>>
+where
+    E: 'static,
+{
+    if !pooling_enabled() || TypeId::of::<E>() != TypeId::of::<Infallible>() {
+        return None;
+    }
+
+    let pooled = NESTED_ROUTE_FUTURE_POOL.with(|pool| pool.borrow_mut().pop())?;
+
+    // SAFETY: just checked `TypeId::of::<E>() == TypeId::of::<Infallible>()`, so
+    // `RouteFuture<Infallible>` and `RouteFuture<E>` are the same type. This is the same
+    // `TypeId`-then-cast trick `Any::downcast` itself uses internally once the `TypeId`s are
+    // known to match; going through `Box<dyn Any>::downcast` here instead, as `Route::downcast`
+    // above does, would mean boxing (and immediately unboxing) `pooled` a second time on every
+    // checkout, which defeats the point of pooling it in the first place.
+    Some(unsafe {
+        std::mem::transmute::<Pin<Box<RouteFuture<Infallible>>>, Pin<Box<RouteFuture<E>>>>(pooled)
+    })
+}
+
+fn checkin<E>(future: Pin<Box<RouteFuture<E>>>)
+where
+    E: 'static,
+{
+    if !pooling_enabled() || TypeId::of::<E>() != TypeId::of::<Infallible>() {
+        return;
+    }
+
+    // SAFETY: see `checkout`.
+    let future = unsafe {
+        std::mem::transmute::<Pin<Box<RouteFuture<E>>>, Pin<Box<RouteFuture<Infallible>>>>(future)
+    };
+
+    NESTED_ROUTE_FUTURE_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < NESTED_ROUTE_FUTURE_POOL_CAP {
+            pool.push(future);
+        }
+    });
+}
+
 impl<E> RouteFuture<E> {
     pub(crate) fn from_future(
         future: Oneshot<BoxCloneService<Request<Body>, Response, E>, Request<Body>>,
@@ -112,6 +306,30 @@ impl<E> RouteFuture<E> {
             kind: RouteFutureKind::Future { future },
             strip_body: false,
             allow_header: None,
+            on_upgrade: None,
+        }
+    }
+
+    pub(crate) fn from_nested(future: RouteFuture<E>) -> Self {
+        Self {
+            kind: RouteFutureKind::Nested {
+                future: PooledRouteFuture::new(future),
+            },
+            strip_body: false,
+            allow_header: None,
+            on_upgrade: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_response(response: Response) -> Self {
+        Self {
+            kind: RouteFutureKind::Response {
+                response: Some(response),
+            },
+            strip_body: false,
+            allow_header: None,
+            on_upgrade: None,
         }
     }
 
@@ -124,6 +342,11 @@ impl<E> RouteFuture<E> {
         self.allow_header = Some(allow_header);
         self
     }
+
+    pub(crate) fn on_upgrade(mut self, on_upgrade: Option<OnUpgrade>) -> Self {
+        self.on_upgrade = on_upgrade;
+        self
+    }
 }
 
 impl<E> Future for RouteFuture<E> {
@@ -142,11 +365,45 @@ impl<E> Future for RouteFuture<E> {
                 Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
                 Poll::Pending => return Poll::Pending,
             },
+            RouteFutureKindProj::Nested { future } => match future.poll(cx) {
+                Poll::Ready(Ok(res)) => res,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            },
             RouteFutureKindProj::Response { response } => {
                 response.take().expect("future polled after completion")
             }
         };
 
+        // Checked *before* `AlreadyPassedThroughRouteFuture` and regardless of whether this
+        // layer has `on_upgrade`: a request normally passes through several stacked `Route`s
+        // (that's the whole point of the marker below), but only the outermost `Route::call`
+        // ever got the real `OnUpgrade` out of the request — every nested one sees it already
+        // taken. The marker, on the other hand, is set by the *innermost* `RouteFuture` (the
+        // first to see the raw, unmarked response), which is exactly the one whose `on_upgrade`
+        // is `None`. So an upgrade response has to bypass the marker entirely: every layer that
+        // doesn't hold `on_upgrade` must leave `ResponseUpgrade` untouched and pass the response
+        // on unmodified, until it reaches the one layer that does.
+        if res.extensions().get::<ResponseUpgrade>().is_some() {
+            if let Some(on_upgrade) = this.on_upgrade.take() {
+                let upgrade = res
+                    .extensions_mut()
+                    .remove::<ResponseUpgrade>()
+                    .expect("just checked above");
+
+                tokio::spawn(async move {
+                    if let Ok(upgraded) = on_upgrade.await {
+                        upgrade.run(upgraded).await;
+                    }
+                });
+            }
+
+            // The response is just the `101 Switching Protocols` head; the real payload is
+            // whatever `upgrade` does with the connection, so none of the buffered-body
+            // post-processing below applies, on this layer or any other.
+            return Poll::Ready(Ok(res));
+        }
+
         if res
             .extensions()
             .get::<AlreadyPassedThroughRouteFuture>()
@@ -216,4 +473,156 @@ mod tests {
         use crate::test_helpers::*;
         assert_send::<Route<()>>();
     }
+
+    #[test]
+    fn new_flattens_nested_route() {
+        let inner = Route::<Infallible>::new(tower::service_fn(|_: Request<Body>| async move {
+            Ok(Response::new(boxed(Empty::new())))
+        }));
+
+        let outer = Route::new(inner);
+
+        assert!(matches!(outer.0, RouteInner::Nested(_)));
+    }
+
+    #[tokio::test]
+    async fn flattened_nested_route_still_runs_post_processing_once() {
+        // Three levels deep: `Route::new` flattens each into the same `RouteInner::Nested` chain
+        // rather than boxing a new layer per level, but every `RouteFuture` in that chain still
+        // has to run its own `poll`, so the response-side post-processing (`Allow`/
+        // `Content-Length`, guarded by `AlreadyPassedThroughRouteFuture`) must still behave as if
+        // there were only one `Route` in the way.
+        let leaf = Route::<Infallible>::new(tower::service_fn(|_: Request<Body>| async move {
+            Ok(Response::new(boxed(Empty::new())))
+        }));
+        let middle = Route::new(leaf);
+        let mut outer = Route::new(middle);
+        assert!(matches!(outer.0, RouteInner::Nested(_)));
+
+        let response = outer
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get(CONTENT_LENGTH).unwrap(),
+            "0",
+            "content-length should be set exactly once, not duplicated or skipped"
+        );
+    }
+
+    #[tokio::test]
+    async fn upgrade_responses_skip_post_processing() {
+        let mut response = Response::new(boxed(Empty::new()));
+        response
+            .extensions_mut()
+            .insert(ResponseUpgrade::new(|_upgraded: Upgraded| async {}));
+
+        let response = RouteFuture::<Infallible>::from_response(response)
+            .await
+            .unwrap();
+
+        assert!(response.headers().get(CONTENT_LENGTH).is_none());
+    }
+
+    #[tokio::test]
+    async fn upgrade_extension_is_preserved_when_this_layer_has_no_on_upgrade() {
+        // `RouteFuture::from_response` never calls `.on_upgrade(...)`, so its `on_upgrade` is
+        // `None` here, mimicking a middle-of-the-stack `Route` whose request no longer carried
+        // the real `OnUpgrade` (an outer layer already took it). It must leave `ResponseUpgrade`
+        // in place for that outer layer to find, instead of discarding it like it used to.
+        let mut response = Response::new(boxed(Empty::new()));
+        response
+            .extensions_mut()
+            .insert(ResponseUpgrade::new(|_upgraded: Upgraded| async {}));
+
+        let response = RouteFuture::<Infallible>::from_response(response)
+            .await
+            .unwrap();
+
+        assert!(response.extensions().get::<ResponseUpgrade>().is_some());
+    }
+
+    #[derive(Clone)]
+    struct PassThrough<S>(S);
+
+    impl<S, B> Service<Request<B>> for PassThrough<S>
+    where
+        S: Service<Request<B>>,
+    {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = S::Future;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.0.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: Request<B>) -> Self::Future {
+            self.0.call(req)
+        }
+    }
+
+    #[tokio::test]
+    async fn upgrade_extension_survives_an_actual_nested_route_call() {
+        // `PassThrough` isn't itself a `Route<E>`, so `Route::new` below can't flatten it via
+        // `Route::downcast` and has to box it instead — which means calling the outer `Route`
+        // really does drive a *second*, independent `<Route as Service>::call` on the inner
+        // `Route`, the same way an arbitrary `Router::layer(...)` middleware does. That's the
+        // shape of the bug this module's `on_upgrade` handling has to survive: only the first of
+        // those two `Service::call`s ever sees a real `OnUpgrade` in the request extensions (here
+        // neither does, since the request carries none — there's no public way to construct a
+        // real `hyper::upgrade::OnUpgrade` outside of a live HTTP/1.1 connection upgrade, so
+        // exercising the spawned callback itself is left to axum's higher-level WebSocket
+        // integration tests). What this proves is that `ResponseUpgrade`, and the "skip the usual
+        // post-processing" behavior it triggers, survive unconsumed across a real nested
+        // `Route::call`, not just a single `RouteFuture::poll`.
+        let inner = Route::<Infallible>::new(tower::service_fn(|_: Request<Body>| async move {
+            let mut response = Response::new(boxed(Empty::new()));
+            response
+                .extensions_mut()
+                .insert(ResponseUpgrade::new(|_upgraded: Upgraded| async {}));
+            Ok(response)
+        }));
+
+        let mut outer = Route::new(PassThrough(inner));
+
+        let response = outer.call(Request::new(Body::empty())).await.unwrap();
+
+        assert!(response.extensions().get::<ResponseUpgrade>().is_some());
+        assert!(response.headers().get(CONTENT_LENGTH).is_none());
+    }
+
+    fn empty_route_future() -> RouteFuture<Infallible> {
+        RouteFuture::from_response(Response::new(boxed(Empty::new())))
+    }
+
+    #[test]
+    fn checkin_then_checkout_reuses_the_same_allocation() {
+        NESTED_ROUTE_FUTURE_POOL.with(|pool| pool.borrow_mut().clear());
+
+        let boxed_future: Pin<Box<RouteFuture<Infallible>>> = Box::pin(empty_route_future());
+        let original_ptr: *const RouteFuture<Infallible> = &*boxed_future;
+
+        checkin(boxed_future);
+        let reused = checkout::<Infallible>().expect("checkin should have populated the pool");
+
+        assert_eq!(&*reused as *const RouteFuture<Infallible>, original_ptr);
+        // The pool should be empty again after the checkout above took its only entry.
+        assert!(checkout::<Infallible>().is_none());
+    }
+
+    #[test]
+    fn dropping_a_pooled_route_future_returns_its_allocation_to_the_pool() {
+        // The request asked for this explicitly: a `RouteFuture` dropped while still pending
+        // (e.g. the client disconnects mid-request) must still give its allocation back, not just
+        // one that's already been polled to `Poll::Ready` and checked in normally.
+        NESTED_ROUTE_FUTURE_POOL.with(|pool| pool.borrow_mut().clear());
+
+        let never_polled = PooledRouteFuture::new(empty_route_future());
+        drop(never_polled);
+
+        let pool_len = NESTED_ROUTE_FUTURE_POOL.with(|pool| pool.borrow().len());
+        assert_eq!(pool_len, 1);
+    }
 }